@@ -0,0 +1,500 @@
+//! The `dealer` module contains the dealer-side logic for a multi-party
+//! aggregated R1CS proof.
+//!
+//! The dealer owns the single shared [`Transcript`] and is the only party
+//! that ever runs [`InnerProductProof::create`]: it collects one message
+//! from each of the `N` parties per round, absorbs the first-round
+//! messages into the transcript in party order (and, in later rounds,
+//! their sums across parties), and broadcasts the resulting challenge back
+//! out. Once every party has returned its [`ProofShare`], the dealer
+//! concatenates the per-party `l_vec`/`r_vec` (each already padded by its
+//! party to a power of two) into a single witness, pads that to a power
+//! of two in turn, and runs one inner-product argument over the whole
+//! thing using the matching concatenation of `bp_gens` generator shares.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use merlin::Transcript;
+
+use super::messages::{ChallengeYZ, FirstRoundCommitments, PolyCommitments, ProofShare};
+use super::R1CSProof;
+
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use inner_product_proof::InnerProductProof;
+use transcript::TranscriptProtocol;
+use util;
+
+/// Used to begin an aggregated multi-party R1CS proof.
+pub struct Dealer {}
+
+impl Dealer {
+    /// Creates a new dealer, given the sizes `n` (number of multipliers),
+    /// `m` (number of committed inputs), and `q` (number of constraints)
+    /// for each of the `N` parties, in the order they will be
+    /// concatenated.
+    ///
+    /// `q` is needed (in addition to `n`) because a party's constraints
+    /// are flattened by `z`'s *constraint* index, which in general
+    /// differs from its multiplier count `n`; see
+    /// [`DealerAwaitingCommitments::receive_commitments`].
+    pub fn new<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: &[usize],
+        m: &[usize],
+        q: &[usize],
+    ) -> Result<DealerAwaitingCommitments<'a, 'b>, R1CSError> {
+        if n.len() != m.len() || n.len() != q.len() {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        let N = n.len();
+        if bp_gens.party_capacity < N {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let padded_n: Vec<usize> = n.iter().map(|n_j| n_j.next_power_of_two()).collect();
+        let total_n: usize = padded_n.iter().sum();
+        let total_m: usize = m.iter().sum();
+
+        transcript.r1cs_domain_sep(total_m as u64);
+
+        Ok(DealerAwaitingCommitments {
+            bp_gens,
+            pc_gens,
+            transcript,
+            n: padded_n,
+            m: m.to_vec(),
+            q: q.to_vec(),
+            total_n,
+            total_m,
+        })
+    }
+}
+
+/// A dealer which has been initialized and is waiting for the parties'
+/// first-round commitments.
+pub struct DealerAwaitingCommitments<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    n: Vec<usize>,
+    m: Vec<usize>,
+    q: Vec<usize>,
+    total_n: usize,
+    total_m: usize,
+}
+
+impl<'a, 'b> DealerAwaitingCommitments<'a, 'b> {
+    /// Receives every party's first-round commitments (in party order),
+    /// absorbs them into the transcript, draws the shared `y, z`
+    /// challenges, and returns the per-party `(y, z)` starting offsets to
+    /// be handed to
+    /// [`PartyAwaitingChallengeYZ::apply_challenge`](super::party::PartyAwaitingChallengeYZ::apply_challenge)
+    /// alongside the broadcast [`ChallengeYZ`].
+    ///
+    /// The `y` offset for party `j` is `y` raised to the sum of the
+    /// *padded multiplier counts* (`n`) of parties `0..j`, since `y`
+    /// indexes the concatenated witness vector the dealer will assemble.
+    /// The `z` offset is `z` raised to one plus the sum of the
+    /// *constraint counts* (`q`) of parties `0..j` (so party 0 starts at
+    /// `z`, as in the single-party flattening), since `z` indexes constraints,
+    /// not multipliers -- the two only coincide when every party's
+    /// constraint count happens to equal its multiplier count.
+    pub fn receive_commitments(
+        self,
+        commitments: Vec<FirstRoundCommitments>,
+    ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, ChallengeYZ, Vec<(Scalar, Scalar)>), R1CSError> {
+        if commitments.len() != self.n.len() {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        // Check that every party actually sent the number of `V`
+        // commitments it was declared to have in `Dealer::new`, before
+        // absorbing anything -- otherwise a party/caller mismatch would
+        // only be visible as a domain separator (`total_m`) that no
+        // longer matches what's actually committed.
+        for (commitment, m_j) in commitments.iter().zip(self.m.iter()) {
+            if commitment.V.len() != *m_j {
+                return Err(R1CSError::InvalidGeneratorsLength);
+            }
+        }
+
+        // Commit every party's `V`s first, in party order, matching the
+        // order the single-party `ProverCS::new` commits them in (before
+        // any `A_I/A_O/S` exist) -- this is what binds the shared
+        // challenges to the specific values each party is proving
+        // against.
+        for FirstRoundCommitments { V, .. } in &commitments {
+            for V_i in V {
+                self.transcript.commit_point(b"V", V_i);
+            }
+        }
+
+        for FirstRoundCommitments { A_I, A_O, S, .. } in &commitments {
+            self.transcript.commit_point(b"A_I", A_I);
+            self.transcript.commit_point(b"A_O", A_O);
+            self.transcript.commit_point(b"S", S);
+        }
+
+        let y = self.transcript.challenge_scalar(b"y");
+        let z = self.transcript.challenge_scalar(b"z");
+
+        let sum_points = |select: fn(&FirstRoundCommitments) -> &CompressedRistretto| -> Result<CompressedRistretto, R1CSError> {
+            let mut total = RistrettoPoint::identity();
+            for c in &commitments {
+                total += select(c)
+                    .decompress()
+                    .ok_or(R1CSError::InvalidGeneratorsLength)?;
+            }
+            Ok(total.compress())
+        };
+
+        let A_I = sum_points(|c| &c.A_I)?;
+        let A_O = sum_points(|c| &c.A_O)?;
+        let S = sum_points(|c| &c.S)?;
+
+        let mut y_start = Scalar::one();
+        let mut z_start = z;
+        let party_offsets: Vec<(Scalar, Scalar)> = self
+            .n
+            .iter()
+            .zip(self.q.iter())
+            .map(|(n_j, q_j)| {
+                let offsets = (y_start, z_start);
+                y_start *= exp(&y, *n_j as u64);
+                z_start *= exp(&z, *q_j as u64);
+                offsets
+            })
+            .collect();
+
+        Ok((
+            DealerAwaitingPolyCommitments {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                transcript: self.transcript,
+                n: self.n,
+                total_n: self.total_n,
+                total_m: self.total_m,
+                y,
+                A_I,
+                A_O,
+                S,
+            },
+            ChallengeYZ { y, z },
+            party_offsets,
+        ))
+    }
+}
+
+/// A dealer which has broadcast `y, z` and is waiting for the parties'
+/// `T_k` polynomial commitments.
+pub struct DealerAwaitingPolyCommitments<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    n: Vec<usize>,
+    total_n: usize,
+    total_m: usize,
+    y: Scalar,
+    A_I: CompressedRistretto,
+    A_O: CompressedRistretto,
+    S: CompressedRistretto,
+}
+
+impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
+    /// Receives every party's `T_k` commitments, sums them homomorphically
+    /// across parties, absorbs the sums into the transcript, and draws
+    /// the shared `x` challenge.
+    pub fn receive_poly_commitments(
+        self,
+        poly_commitments: Vec<PolyCommitments>,
+    ) -> Result<(DealerAwaitingProofShares<'a, 'b>, Scalar), R1CSError> {
+        if poly_commitments.len() != self.n.len() {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let sum = |select: fn(&PolyCommitments) -> &CompressedRistretto| -> Result<CompressedRistretto, R1CSError> {
+            let mut total = RistrettoPoint::identity();
+            for pc in &poly_commitments {
+                total += select(pc)
+                    .decompress()
+                    .ok_or(R1CSError::InvalidGeneratorsLength)?;
+            }
+            Ok(total.compress())
+        };
+
+        let T_1 = sum(|pc| &pc.T_1)?;
+        let T_3 = sum(|pc| &pc.T_3)?;
+        let T_4 = sum(|pc| &pc.T_4)?;
+        let T_5 = sum(|pc| &pc.T_5)?;
+        let T_6 = sum(|pc| &pc.T_6)?;
+
+        self.transcript.commit_point(b"T_1", &T_1);
+        self.transcript.commit_point(b"T_3", &T_3);
+        self.transcript.commit_point(b"T_4", &T_4);
+        self.transcript.commit_point(b"T_5", &T_5);
+        self.transcript.commit_point(b"T_6", &T_6);
+
+        let x = self.transcript.challenge_scalar(b"x");
+
+        Ok((
+            DealerAwaitingProofShares {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                transcript: self.transcript,
+                n: self.n,
+                total_n: self.total_n,
+                total_m: self.total_m,
+                y: self.y,
+                A_I: self.A_I,
+                A_O: self.A_O,
+                S: self.S,
+                T_1,
+                T_3,
+                T_4,
+                T_5,
+                T_6,
+            },
+            x,
+        ))
+    }
+}
+
+/// A dealer which has broadcast `x` and is waiting for the parties' final
+/// proof shares.
+pub struct DealerAwaitingProofShares<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    n: Vec<usize>,
+    total_n: usize,
+    total_m: usize,
+    y: Scalar,
+    A_I: CompressedRistretto,
+    A_O: CompressedRistretto,
+    S: CompressedRistretto,
+    T_1: CompressedRistretto,
+    T_3: CompressedRistretto,
+    T_4: CompressedRistretto,
+    T_5: CompressedRistretto,
+    T_6: CompressedRistretto,
+}
+
+impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
+    /// Receives every party's proof share (in party order), sums the
+    /// scalar components, concatenates the vector components, and
+    /// assembles the final aggregated [`R1CSProof`].
+    pub fn receive_shares(self, shares: Vec<ProofShare>) -> Result<R1CSProof, R1CSError> {
+        if shares.len() != self.n.len() {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let t_x = shares.iter().map(|s| s.t_x).sum();
+        let t_x_blinding = shares.iter().map(|s| s.t_x_blinding).sum();
+        let e_blinding = shares.iter().map(|s| s.e_blinding).sum();
+
+        self.transcript.commit_scalar(b"t_x", &t_x);
+        self.transcript
+            .commit_scalar(b"t_x_blinding", &t_x_blinding);
+        self.transcript.commit_scalar(b"e_blinding", &e_blinding);
+
+        let w = self.transcript.challenge_scalar(b"w");
+        let Q = w * self.pc_gens.B;
+
+        let mut l_vec = Vec::with_capacity(self.total_n);
+        let mut r_vec = Vec::with_capacity(self.total_n);
+        let mut G_vec = Vec::with_capacity(self.total_n);
+        let mut H_vec = Vec::with_capacity(self.total_n);
+        let mut y_inv_vec = Vec::with_capacity(self.total_n);
+
+        let mut y_start = Scalar::one();
+        let y = self.y;
+
+        for (j, (share, n_j)) in shares.into_iter().zip(self.n.iter()).enumerate() {
+            let gens = self.bp_gens.share(j);
+            l_vec.extend(share.l_vec);
+            r_vec.extend(share.r_vec);
+            G_vec.extend(gens.G(*n_j).cloned());
+            H_vec.extend(gens.H(*n_j).cloned());
+
+            let y_inv = y.invert();
+            let y_start_inv = y_start.invert();
+            y_inv_vec.extend(util::exp_iter(y_inv).take(*n_j).map(|p| p * y_start_inv));
+            y_start *= exp(&y, *n_j as u64);
+        }
+
+        let ipp_proof =
+            InnerProductProof::create(self.transcript, &Q, &y_inv_vec, G_vec, H_vec, l_vec, r_vec);
+
+        // The aggregated multi-party protocol doesn't support deferred,
+        // randomized phase-2 constraints (that would require a fourth
+        // message round), so every party's witness is entirely phase 1
+        // and phase 2 is trivially empty.
+        let A_I2 = RistrettoPoint::identity().compress();
+        let A_O2 = RistrettoPoint::identity().compress();
+        let S2 = RistrettoPoint::identity().compress();
+
+        Ok(R1CSProof {
+            A_I1: self.A_I,
+            A_O1: self.A_O,
+            S1: self.S,
+            A_I2,
+            A_O2,
+            S2,
+            T_1: self.T_1,
+            T_3: self.T_3,
+            T_4: self.T_4,
+            T_5: self.T_5,
+            T_6: self.T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+}
+
+/// Computes `base^exp` via repeated squaring.
+fn exp(base: &Scalar, mut exp: u64) -> Scalar {
+    let mut result = Scalar::one();
+    let mut base = *base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circuit_proof::party::Party;
+    use circuit_proof::prover::ProverCS;
+    use circuit_proof::{ConstraintSystem, LinearCombination};
+    use rand::thread_rng;
+
+    /// A single party, with no multipliers (padded to one slot) and one
+    /// committed value that the dealer must bind into the shared
+    /// transcript: swapping the party's committed value (holding its blinding factor and
+    /// witness geometry fixed) must change the derived `y, z`
+    /// challenges. Before `V` was absorbed, the challenges depended only
+    /// on `A_I, A_O, S`, which don't depend on which value is committed.
+    fn first_round_challenges(v: Scalar) -> (Scalar, Scalar) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+
+        let mut party_transcript = Transcript::new(b"dealer-test-party");
+        let (cs, _vars, _commitments) = ProverCS::new(
+            &bp_gens,
+            &pc_gens,
+            &mut party_transcript,
+            vec![v],
+            vec![Scalar::from(11u64)],
+        );
+        let party = Party::new(&bp_gens, &pc_gens, cs);
+        let (_party, first_round) = party.assign_position(0, &mut thread_rng()).unwrap();
+
+        let mut dealer_transcript = Transcript::new(b"dealer-test-aggregated");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut dealer_transcript, &[0], &[1], &[0]).unwrap();
+        let (_dealer, challenge, _offsets) = dealer.receive_commitments(vec![first_round]).unwrap();
+
+        (challenge.y, challenge.z)
+    }
+
+    #[test]
+    fn receive_commitments_binds_challenges_to_committed_value() {
+        let (y_a, z_a) = first_round_challenges(Scalar::from(3u64));
+        let (y_b, z_b) = first_round_challenges(Scalar::from(4u64));
+
+        assert!(y_a != y_b || z_a != z_b);
+    }
+
+    /// `z` indexes constraints, not multipliers: a party with more
+    /// constraints than multipliers (or vice versa) must still advance
+    /// the next party's starting power of `z` by its own constraint
+    /// count, not its multiplier count.
+    #[test]
+    fn receive_commitments_advances_z_by_constraint_count_not_multiplier_count() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(4, 2);
+
+        // Party 0: 2 multipliers, but 3 constraints.
+        let mut t0 = Transcript::new(b"dealer-test-party-0");
+        let (mut cs0, _vars0, _commitments0) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut t0, vec![], vec![]);
+        let (l0, r0, _o0) = cs0
+            .assign_multiplier(Scalar::zero().into(), Scalar::zero().into(), Scalar::zero().into())
+            .unwrap();
+        let (_l1, _r1, o1) = cs0
+            .assign_multiplier(Scalar::zero().into(), Scalar::zero().into(), Scalar::zero().into())
+            .unwrap();
+        cs0.add_constraint(LinearCombination::from(l0));
+        cs0.add_constraint(LinearCombination::from(r0));
+        cs0.add_constraint(LinearCombination::from(o1));
+        let party0 = Party::new(&bp_gens, &pc_gens, cs0);
+
+        // Party 1: 1 multiplier, 1 constraint.
+        let mut t1 = Transcript::new(b"dealer-test-party-1");
+        let (mut cs1, _vars1, _commitments1) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut t1, vec![], vec![]);
+        let (l2, _r2, _o2) = cs1
+            .assign_multiplier(Scalar::zero().into(), Scalar::zero().into(), Scalar::zero().into())
+            .unwrap();
+        cs1.add_constraint(LinearCombination::from(l2));
+        let party1 = Party::new(&bp_gens, &pc_gens, cs1);
+
+        let mut rng = thread_rng();
+        let (_party0, first0) = party0.assign_position(0, &mut rng).unwrap();
+        let (_party1, first1) = party1.assign_position(1, &mut rng).unwrap();
+
+        let mut dealer_transcript = Transcript::new(b"dealer-test-aggregated");
+        let dealer =
+            Dealer::new(&bp_gens, &pc_gens, &mut dealer_transcript, &[2, 1], &[0, 0], &[3, 1]).unwrap();
+        let (_dealer, challenge, offsets) = dealer.receive_commitments(vec![first0, first1]).unwrap();
+
+        assert_eq!(offsets[0], (Scalar::one(), challenge.z));
+        assert_eq!(offsets[1].0, exp(&challenge.y, 2));
+        // z_start begins at z^1 (not z^0), so after party 0's q_0 = 3
+        // constraints it's advanced to z^(1 + 3) = z^4, not z^3.
+        assert_eq!(offsets[1].1, exp(&challenge.z, 4));
+    }
+
+    /// A party that actually sends a different number of `V` commitments
+    /// than the dealer was told (via `m`) to expect must be rejected,
+    /// rather than silently accepted with only the (now-wrong) domain
+    /// separator reflecting the mismatch.
+    #[test]
+    fn receive_commitments_rejects_a_party_v_count_mismatch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+
+        let mut party_transcript = Transcript::new(b"dealer-test-party");
+        let (cs, _vars, _commitments) = ProverCS::new(
+            &bp_gens,
+            &pc_gens,
+            &mut party_transcript,
+            vec![Scalar::from(3u64)],
+            vec![Scalar::from(11u64)],
+        );
+        let party = Party::new(&bp_gens, &pc_gens, cs);
+        let (_party, first_round) = party.assign_position(0, &mut thread_rng()).unwrap();
+
+        // Dealer is told to expect 2 commitments from this party, but it
+        // only ever sent 1.
+        let mut dealer_transcript = Transcript::new(b"dealer-test-aggregated");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut dealer_transcript, &[0], &[2], &[0]).unwrap();
+
+        match dealer.receive_commitments(vec![first_round]) {
+            Err(R1CSError::InvalidGeneratorsLength) => {}
+            other => panic!("expected InvalidGeneratorsLength, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! The `messages` module contains the messages passed between a
+//! [`Dealer`](super::dealer::Dealer) and the [`Party`](super::party::Party)
+//! instances during an aggregated multi-party R1CS proof.
+//!
+//! Unlike the single-party [`ProverCS::prove`](super::prover::ProverCS::prove),
+//! the aggregated protocol cannot have each party maintain its own copy of
+//! the transcript, since the parties do not see each other's commitments
+//! directly.  Instead, the dealer collects one message from each party per
+//! round, absorbs it into the shared transcript, and broadcasts the
+//! resulting challenge back to every party.
+
+#![allow(non_snake_case)]
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+/// A party's first-round message: the Pedersen commitments to its
+/// external inputs, and its local multiplier-wire commitments.
+#[derive(Clone, Debug)]
+pub struct FirstRoundCommitments {
+    pub(super) V: Vec<CompressedRistretto>,
+    pub(super) A_I: CompressedRistretto,
+    pub(super) A_O: CompressedRistretto,
+    pub(super) S: CompressedRistretto,
+}
+
+/// The dealer's first-round response: the shared `y, z` challenges.
+#[derive(Copy, Clone, Debug)]
+pub struct ChallengeYZ {
+    pub(super) y: Scalar,
+    pub(super) z: Scalar,
+}
+
+/// A party's second-round message: its local `T_k` polynomial commitments.
+#[derive(Clone, Debug)]
+pub struct PolyCommitments {
+    pub(super) T_1: CompressedRistretto,
+    pub(super) T_3: CompressedRistretto,
+    pub(super) T_4: CompressedRistretto,
+    pub(super) T_5: CompressedRistretto,
+    pub(super) T_6: CompressedRistretto,
+}
+
+/// A party's third and final message: its share of the proof.
+#[derive(Clone, Debug)]
+pub struct ProofShare {
+    pub(super) t_x: Scalar,
+    pub(super) t_x_blinding: Scalar,
+    pub(super) e_blinding: Scalar,
+    pub(super) l_vec: Vec<Scalar>,
+    pub(super) r_vec: Vec<Scalar>,
+}
@@ -0,0 +1,318 @@
+//! The `party` module contains the party-side logic for a multi-party
+//! aggregated R1CS proof, analogous to the `Party` typestate used by the
+//! aggregated range-proof protocol.
+//!
+//! A `Party` takes ownership of a [`ProverCS`] that the caller has already
+//! used to build its own private share of the constraint system (its own
+//! multipliers, its own committed inputs, and any constraints that only
+//! reference them), then drives it through three rounds orchestrated by a
+//! [`Dealer`](super::dealer::Dealer): first sending its commitments `V` to
+//! its own external inputs alongside its local `A_I, A_O, S` commitments,
+//! then (once it learns the shared `y, z`) its local `T_k`
+//! commitments, and finally (once it learns the shared `x`) its share of
+//! the proof.
+//!
+//! Each state being its own type, consumed by value on every transition,
+//! ensures that a party can't, e.g., skip
+//! straight from commitments to a proof share without consuming the
+//! dealer's challenges in order.
+
+#![allow(non_snake_case)]
+
+use std::iter;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use rand::{CryptoRng, RngCore};
+
+use super::messages::{ChallengeYZ, FirstRoundCommitments, PolyCommitments, ProofShare};
+use super::prover::ProverCS;
+use super::{LinearCombination, Variable};
+
+use errors::R1CSError;
+use generators::{BulletproofGens, PedersenGens};
+use util;
+
+/// A party, prior to learning its position `j` in the aggregated proof.
+pub struct Party<'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    a_L: Vec<Scalar>,
+    a_R: Vec<Scalar>,
+    a_O: Vec<Scalar>,
+    v: Vec<Scalar>,
+    v_blinding: Vec<Scalar>,
+    constraints: Vec<LinearCombination>,
+}
+
+impl<'b> Party<'b> {
+    /// Takes ownership of a [`ProverCS`] that the caller has finished
+    /// building (all of this party's multipliers and constraints have
+    /// been assigned), and wraps it for use in an aggregated proof.
+    pub fn new<'a>(bp_gens: &'b BulletproofGens, pc_gens: &'b PedersenGens, cs: ProverCS<'a, 'b>) -> Self {
+        let (a_L, a_R, a_O, v, v_blinding, constraints) = cs.into_witness();
+        Party {
+            bp_gens,
+            pc_gens,
+            a_L,
+            a_R,
+            a_O,
+            v,
+            v_blinding,
+            constraints,
+        }
+    }
+
+    /// Assigns this party its position `j` in the aggregated proof, pads
+    /// its local witness to a power of two, and produces its first-round
+    /// commitments.
+    ///
+    /// `j` also determines which disjoint block of `bp_gens` this party
+    /// draws its `G, H` generators from, via [`BulletproofGens::share`].
+    pub fn assign_position<R: RngCore + CryptoRng>(
+        mut self,
+        j: usize,
+        rng: &mut R,
+    ) -> Result<(PartyAwaitingChallengeYZ<'b>, FirstRoundCommitments), R1CSError> {
+        let n = self.a_L.len();
+        if !n.is_power_of_two() {
+            // Also catches `n == 0`: a party with no multipliers still
+            // needs to contribute a single padded slot, since the dealer
+            // computes every party's padded length the same way via
+            // `n.next_power_of_two()`.
+            let pad = n.next_power_of_two() - n;
+            self.a_L.extend(iter::repeat(Scalar::zero()).take(pad));
+            self.a_R.extend(iter::repeat(Scalar::zero()).take(pad));
+            self.a_O.extend(iter::repeat(Scalar::zero()).take(pad));
+        }
+        let n = self.a_L.len();
+
+        if self.bp_gens.party_capacity <= j || self.bp_gens.gens_capacity < n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let i_blinding = Scalar::random(rng);
+        let o_blinding = Scalar::random(rng);
+        let s_blinding = Scalar::random(rng);
+        let s_L: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let s_R: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+
+        let share = self.bp_gens.share(j);
+        let A_I = RistrettoPoint::multiscalar_mul(
+            iter::once(&i_blinding)
+                .chain(self.a_L.iter())
+                .chain(self.a_R.iter()),
+            iter::once(&self.pc_gens.B_blinding)
+                .chain(share.G(n))
+                .chain(share.H(n)),
+        )
+        .compress();
+
+        let A_O = RistrettoPoint::multiscalar_mul(
+            iter::once(&o_blinding).chain(self.a_O.iter()),
+            iter::once(&self.pc_gens.B_blinding).chain(share.G(n)),
+        )
+        .compress();
+
+        let S = RistrettoPoint::multiscalar_mul(
+            iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
+            iter::once(&self.pc_gens.B_blinding)
+                .chain(share.G(n))
+                .chain(share.H(n)),
+        )
+        .compress();
+
+        let V = self
+            .v
+            .iter()
+            .zip(self.v_blinding.iter())
+            .map(|(v_i, v_blinding_i)| self.pc_gens.commit(*v_i, *v_blinding_i).compress())
+            .collect();
+
+        Ok((
+            PartyAwaitingChallengeYZ {
+                pc_gens: self.pc_gens,
+                a_L: self.a_L,
+                a_R: self.a_R,
+                a_O: self.a_O,
+                v: self.v,
+                v_blinding: self.v_blinding,
+                constraints: self.constraints,
+                i_blinding,
+                o_blinding,
+                s_blinding,
+                s_L,
+                s_R,
+            },
+            FirstRoundCommitments { V, A_I, A_O, S },
+        ))
+    }
+}
+
+/// A party which has sent its first-round commitments and is waiting to
+/// learn the shared `y, z` challenges from the dealer.
+pub struct PartyAwaitingChallengeYZ<'b> {
+    pc_gens: &'b PedersenGens,
+    a_L: Vec<Scalar>,
+    a_R: Vec<Scalar>,
+    a_O: Vec<Scalar>,
+    v: Vec<Scalar>,
+    v_blinding: Vec<Scalar>,
+    constraints: Vec<LinearCombination>,
+    i_blinding: Scalar,
+    o_blinding: Scalar,
+    s_blinding: Scalar,
+    s_L: Vec<Scalar>,
+    s_R: Vec<Scalar>,
+}
+
+impl<'b> PartyAwaitingChallengeYZ<'b> {
+    /// Uses the shared `y, z` challenges to flatten this party's local
+    /// constraints and compute its `T_k` commitments.
+    ///
+    /// `y_start` is `y` raised to this party's starting offset in the
+    /// concatenated witness vector the dealer will assemble; `z_start` is
+    /// the analogous starting power of `z` for this party's first
+    /// constraint. Passing these in (rather than each party picking its
+    /// own offset) is what keeps the flattened `wL/wR/wO` lined up with
+    /// the shared generators once the dealer concatenates every party's
+    /// `l_vec`/`r_vec`.
+    pub fn apply_challenge<R: RngCore + CryptoRng>(
+        self,
+        y_start: Scalar,
+        z_start: Scalar,
+        challenge: ChallengeYZ,
+        rng: &mut R,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitments) {
+        let n = self.a_L.len();
+        let m = self.v.len();
+        let y = challenge.y;
+        let z = challenge.z;
+
+        let mut wL = vec![Scalar::zero(); n];
+        let mut wR = vec![Scalar::zero(); n];
+        let mut wO = vec![Scalar::zero(); n];
+        let mut wV = vec![Scalar::zero(); m];
+
+        let mut exp_z = z_start;
+        for lc in self.constraints.iter() {
+            for (var, coeff) in &lc.terms {
+                match var {
+                    Variable::MultiplierLeft(i) => wL[*i] += exp_z * coeff,
+                    Variable::MultiplierRight(i) => wR[*i] += exp_z * coeff,
+                    Variable::MultiplierOutput(i) => wO[*i] += exp_z * coeff,
+                    Variable::Committed(i) => wV[*i] -= exp_z * coeff,
+                    Variable::One() => {}
+                }
+            }
+            exp_z *= z;
+        }
+
+        let mut l_poly = util::VecPoly3::zero(n);
+        let mut r_poly = util::VecPoly3::zero(n);
+
+        let y_inv = y.invert();
+        let y_start_inv = y_start.invert();
+        let exp_y_inv: Vec<Scalar> = util::exp_iter(y_inv)
+            .take(n)
+            .map(|y_inv_i| y_inv_i * y_start_inv)
+            .collect();
+        let mut exp_y = y_start;
+
+        for i in 0..n {
+            l_poly.1[i] = self.a_L[i] + exp_y_inv[i] * wR[i];
+            l_poly.2[i] = self.a_O[i];
+            l_poly.3[i] = self.s_L[i];
+            r_poly.0[i] = wO[i] - exp_y;
+            r_poly.1[i] = exp_y * self.a_R[i] + wL[i];
+            r_poly.3[i] = exp_y * self.s_R[i];
+            exp_y *= y;
+        }
+
+        let t_poly = l_poly.inner_product(&r_poly);
+
+        let t_1_blinding = Scalar::random(rng);
+        let t_3_blinding = Scalar::random(rng);
+        let t_4_blinding = Scalar::random(rng);
+        let t_5_blinding = Scalar::random(rng);
+        let t_6_blinding = Scalar::random(rng);
+
+        let T_1 = self.pc_gens.commit(t_poly.t1, t_1_blinding).compress();
+        let T_3 = self.pc_gens.commit(t_poly.t3, t_3_blinding).compress();
+        let T_4 = self.pc_gens.commit(t_poly.t4, t_4_blinding).compress();
+        let T_5 = self.pc_gens.commit(t_poly.t5, t_5_blinding).compress();
+        let T_6 = self.pc_gens.commit(t_poly.t6, t_6_blinding).compress();
+
+        let t_2_blinding = wV
+            .iter()
+            .zip(self.v_blinding.iter())
+            .map(|(c, v_blinding)| c * v_blinding)
+            .sum();
+
+        (
+            PartyAwaitingPolyChallenge {
+                t_poly,
+                t_1_blinding,
+                t_2_blinding,
+                t_3_blinding,
+                t_4_blinding,
+                t_5_blinding,
+                t_6_blinding,
+                i_blinding: self.i_blinding,
+                o_blinding: self.o_blinding,
+                s_blinding: self.s_blinding,
+                l_poly,
+                r_poly,
+            },
+            PolyCommitments {
+                T_1,
+                T_3,
+                T_4,
+                T_5,
+                T_6,
+            },
+        )
+    }
+}
+
+/// A party which has sent its poly commitments and is waiting to learn
+/// the shared `x` challenge from the dealer.
+pub struct PartyAwaitingPolyChallenge {
+    t_poly: util::Poly6,
+    l_poly: util::VecPoly3,
+    r_poly: util::VecPoly3,
+    t_1_blinding: Scalar,
+    t_2_blinding: Scalar,
+    t_3_blinding: Scalar,
+    t_4_blinding: Scalar,
+    t_5_blinding: Scalar,
+    t_6_blinding: Scalar,
+    i_blinding: Scalar,
+    o_blinding: Scalar,
+    s_blinding: Scalar,
+}
+
+impl PartyAwaitingPolyChallenge {
+    /// Uses the shared `x` challenge to compute this party's share of the
+    /// final proof: `t_x`, `t_x_blinding`, `e_blinding`, and its `l_vec`,
+    /// `r_vec`.
+    pub fn apply_challenge(self, x: Scalar) -> ProofShare {
+        let t_blinding_poly = util::Poly6 {
+            t1: self.t_1_blinding,
+            t2: self.t_2_blinding,
+            t3: self.t_3_blinding,
+            t4: self.t_4_blinding,
+            t5: self.t_5_blinding,
+            t6: self.t_6_blinding,
+        };
+
+        ProofShare {
+            t_x: self.t_poly.eval(x),
+            t_x_blinding: t_blinding_poly.eval(x),
+            e_blinding: x * (self.i_blinding + x * (self.o_blinding + x * self.s_blinding)),
+            l_vec: self.l_poly.eval(x),
+            r_vec: self.r_poly.eval(x),
+        }
+    }
+}
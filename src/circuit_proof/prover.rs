@@ -11,8 +11,25 @@ use super::{ConstraintSystem, LinearCombination, R1CSProof, Variable};
 use errors::R1CSError;
 use generators::{BulletproofGens, PedersenGens};
 use inner_product_proof::InnerProductProof;
+use rand::{thread_rng, CryptoRng, RngCore};
 use transcript::TranscriptProtocol;
 
+/// The blinding factors and commitments generated for the first phase of
+/// a [`ProverCS`]'s low-level witness wires, i.e. the `n1` multipliers
+/// that existed at the point [`ProverCS::specify_randomized_constraints`]
+/// was called (or all of them, if it was never called).
+struct Phase1Commitments {
+    n1: usize,
+    i_blinding1: Scalar,
+    o_blinding1: Scalar,
+    s_blinding1: Scalar,
+    s_L1: Vec<Scalar>,
+    s_R1: Vec<Scalar>,
+    A_I1: CompressedRistretto,
+    A_O1: CompressedRistretto,
+    S1: CompressedRistretto,
+}
+
 /// A [`ConstraintSystem`] implementation for use by the prover.
 ///
 /// The lifecycle of a `ProverCS` is as follows.  The proving code
@@ -36,6 +53,7 @@ pub struct ProverCS<'a, 'b> {
     a_O: Vec<Scalar>,
     v: Vec<Scalar>,
     v_blinding: Vec<Scalar>,
+    phase1: Option<Phase1Commitments>,
 }
 
 impl<'a, 'b> ConstraintSystem for ProverCS<'a, 'b> {
@@ -73,8 +91,11 @@ impl<'a, 'b> ConstraintSystem for ProverCS<'a, 'b> {
     }
 
     fn add_constraint(&mut self, lc: LinearCombination) {
-        // TODO: check that the linear combinations are valid
-        // (e.g. that variables are valid, that the linear combination evals to 0 for prover, etc).
+        // TODO: check that variables are valid (e.g. that they belong to
+        // this constraint system). Whether the linear combination evals
+        // to 0 for the prover's witness is checked eagerly only by
+        // `ProverCS::prove_with_checks`, not here, so that the common
+        // path stays free of an extra per-constraint evaluation.
         self.constraints.push(lc);
     }
 
@@ -156,6 +177,7 @@ impl<'a, 'b> ProverCS<'a, 'b> {
             a_L: vec![],
             a_R: vec![],
             a_O: vec![],
+            phase1: None,
         };
 
         (cs, variables, commitments)
@@ -211,14 +233,236 @@ impl<'a, 'b> ProverCS<'a, 'b> {
         (wL, wR, wO, wV)
     }
 
-    /// Consume this `ConstraintSystem` to produce a proof.
-    pub fn prove(mut self) -> Result<R1CSProof, R1CSError> {
+    /// Consumes this `ProverCS`, returning its raw witness and
+    /// constraint data without driving the single-party [`prove`]
+    /// transcript flow.
+    ///
+    /// This is used by [`Party`](super::party::Party) to fold a
+    /// party's locally-built constraint system into a multi-party
+    /// aggregated proof, where the transcript interactions are
+    /// instead driven by a [`Dealer`](super::dealer::Dealer).
+    ///
+    /// [`prove`]: ProverCS::prove
+    pub(crate) fn into_witness(
+        self,
+    ) -> (
+        Vec<Scalar>,
+        Vec<Scalar>,
+        Vec<Scalar>,
+        Vec<Scalar>,
+        Vec<Scalar>,
+        Vec<LinearCombination>,
+    ) {
+        (
+            self.a_L,
+            self.a_R,
+            self.a_O,
+            self.v,
+            self.v_blinding,
+            self.constraints,
+        )
+    }
+
+    /// Commits to the low-level witness wires that exist right now (the
+    /// first `n1` multipliers), drawing fresh blinding factors from a
+    /// transcript-derived RNG finalized with `rng` and absorbing
+    /// `A_I1, A_O1, S1` into the transcript.
+    ///
+    /// This is a no-op from the caller's perspective if it has already
+    /// been called once; [`ProverCS::prove_with_rng`] calls it implicitly
+    /// (passing through its own `rng`) if the constraint system never
+    /// went through
+    /// [`specify_randomized_constraints`](ProverCS::specify_randomized_constraints),
+    /// so that the single-phase case is just the special case where
+    /// phase one covers every multiplier the caller assigned (only the
+    /// power-of-two padding then falls into phase two).
+    ///
+    /// Checks `n1` against `bp_gens.gens_capacity` before committing
+    /// anything, so that a too-large phase 1 can't leave the transcript
+    /// partially mutated or build `A_I1/A_O1/S1` from a silently
+    /// truncated generator vector.
+    fn commit_phase1<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), R1CSError> {
+        if self.phase1.is_some() {
+            return Ok(());
+        }
+
         use std::iter;
-        use util;
 
-        // 0. Pad zeros to the next power of two (or do that implicitly when creating vectors)
+        let n1 = self.a_L.len();
+        if self.bp_gens.gens_capacity < n1 {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        let mut rng = {
+            let mut builder = self.transcript.build_rng();
+            for v_b in &self.v_blinding {
+                builder = builder.commit_witness_bytes(b"v_blinding", v_b.as_bytes());
+            }
+            builder.finalize(rng)
+        };
+
+        let i_blinding1 = Scalar::random(&mut rng);
+        let o_blinding1 = Scalar::random(&mut rng);
+        let s_blinding1 = Scalar::random(&mut rng);
+        let s_L1: Vec<Scalar> = (0..n1).map(|_| Scalar::random(&mut rng)).collect();
+        let s_R1: Vec<Scalar> = (0..n1).map(|_| Scalar::random(&mut rng)).collect();
+
+        let gens = self.bp_gens.share(0);
+
+        let A_I1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&i_blinding1)
+                .chain(self.a_L.iter())
+                .chain(self.a_R.iter()),
+            iter::once(&self.pc_gens.B_blinding)
+                .chain(gens.G(n1))
+                .chain(gens.H(n1)),
+        )
+        .compress();
+
+        let A_O1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&o_blinding1).chain(self.a_O.iter()),
+            iter::once(&self.pc_gens.B_blinding).chain(gens.G(n1)),
+        )
+        .compress();
+
+        let S1 = RistrettoPoint::multiscalar_mul(
+            iter::once(&s_blinding1)
+                .chain(s_L1.iter())
+                .chain(s_R1.iter()),
+            iter::once(&self.pc_gens.B_blinding)
+                .chain(gens.G(n1))
+                .chain(gens.H(n1)),
+        )
+        .compress();
+
+        self.transcript.commit_point(b"A_I1", &A_I1);
+        self.transcript.commit_point(b"A_O1", &A_O1);
+        self.transcript.commit_point(b"S1", &S1);
+
+        self.phase1 = Some(Phase1Commitments {
+            n1,
+            i_blinding1,
+            o_blinding1,
+            s_blinding1,
+            s_L1,
+            s_R1,
+            A_I1,
+            A_O1,
+            S1,
+        });
+        Ok(())
+    }
+
+    /// Closes out the first phase of low-level witness wires by calling
+    /// [`commit_phase1`](ProverCS::commit_phase1), draws a randomization
+    /// challenge bound to that commitment, and passes it to `callback`
+    /// along with `self` so that `callback` can allocate a second phase
+    /// of multipliers and constraints (for gadgets like shuffles or set
+    /// membership that need a challenge drawn after the witness wires
+    /// are committed).
+    ///
+    /// `rng` supplies the phase-1 blinding factors, exactly like the
+    /// `rng` passed to [`ProverCS::prove_with_rng`] supplies phase 2's;
+    /// passing the same seeded `rng` to both calls makes the whole
+    /// two-phase proof deterministic, which callers that never invoke
+    /// this method get for free via `prove_with_rng` alone.
+    ///
+    /// May only be called once per `ProverCS`; calling it twice, or
+    /// calling it after any phase-2 constraints have already been added
+    /// some other way, returns [`R1CSError::InvalidR1CSConstruction`].
+    pub fn specify_randomized_constraints<F, R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        callback: F,
+    ) -> Result<(), R1CSError>
+    where
+        F: FnOnce(&mut Self, Scalar) -> Result<(), R1CSError>,
+    {
+        if self.phase1.is_some() {
+            return Err(R1CSError::InvalidR1CSConstruction);
+        }
+        self.commit_phase1(rng)?;
+        let challenge = self.transcript.challenge_scalar(b"randomization");
+        callback(self, challenge)
+    }
+
+    /// Evaluates `lc` against this prover's fully-assigned witness,
+    /// resolving `MultiplierLeft/Right/Output`, `Committed`, and `One`
+    /// terms against `a_L`/`a_R`/`a_O`/`v` respectively.
+    fn eval_lc(&self, lc: &LinearCombination) -> Scalar {
+        lc.terms
+            .iter()
+            .map(|(var, coeff)| {
+                coeff
+                    * match var {
+                        Variable::MultiplierLeft(i) => self.a_L[*i],
+                        Variable::MultiplierRight(i) => self.a_R[*i],
+                        Variable::MultiplierOutput(i) => self.a_O[*i],
+                        Variable::Committed(i) => self.v[*i],
+                        Variable::One() => Scalar::one(),
+                    }
+            })
+            .sum()
+    }
+
+    /// Like [`ProverCS::prove`], but first checks that every constraint
+    /// added via [`ConstraintSystem::add_constraint`] evaluates to zero
+    /// against this prover's witness.
+    ///
+    /// If some constraint does not evaluate to zero, this returns
+    /// [`R1CSError::ConstraintNotSatisfied`] naming the index of the
+    /// first such constraint and its nonzero residual, rather than
+    /// letting a mis-specified gadget fail silently until verification.
+    /// This check is `O(n)` in the total size of the constraint system
+    /// and is intended for debugging gadget code, not for production use.
+    pub fn prove_with_checks(self) -> Result<R1CSProof, R1CSError> {
+        for (index, lc) in self.constraints.iter().enumerate() {
+            let residual = self.eval_lc(lc);
+            if residual != Scalar::zero() {
+                return Err(R1CSError::ConstraintNotSatisfied { index, residual });
+            }
+        }
+        self.prove()
+    }
 
-        // If the number of multiplications is not 0 or a power of 2, then pad the circuit.
+    /// Consume this `ConstraintSystem` to produce a proof, using
+    /// `rand::thread_rng()` as the source of supplementary randomness
+    /// for the blinding factors. See [`ProverCS::prove_with_rng`] for a
+    /// variant that takes an explicit RNG.
+    pub fn prove(self) -> Result<R1CSProof, R1CSError> {
+        self.prove_with_rng(&mut thread_rng())
+    }
+
+    /// Consume this `ConstraintSystem` to produce a proof, using `rng`
+    /// as the source of supplementary randomness for the blinding
+    /// factors (`i_blinding`, `o_blinding`, `s_blinding`, `s_L`, `s_R`,
+    /// and the `t_*_blinding`s), rather than `rand::thread_rng()`.
+    ///
+    /// This makes it possible to produce deterministic test vectors, to
+    /// reproduce a proving run under fuzzing, or to prove in an
+    /// environment without an OS thread RNG, by supplying a seeded or
+    /// otherwise explicit `R: RngCore + CryptoRng`.
+    pub fn prove_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        rng: &mut R,
+    ) -> Result<R1CSProof, R1CSError> {
+        use std::iter;
+        use util;
+
+        // 0. Commit phase 1 if the caller never called
+        // `specify_randomized_constraints` -- in that case every
+        // multiplier added so far is phase 1, and phase 2 holds only the
+        // power-of-two padding added below (if any). Pass
+        // our `rng` through so the common (single-phase) case draws its
+        // blinding factors from the caller-supplied RNG too, rather than
+        // from `thread_rng()`.
+        self.commit_phase1(rng)?;
+        let phase1 = self.phase1.take().expect("commit_phase1 always sets self.phase1");
+        let n1 = phase1.n1;
+
+        // Pad zeros to the next power of two. Any padding, like any
+        // constraints added after `specify_randomized_constraints`, falls
+        // into phase 2.
         let temp_n = self.a_L.len();
         if !(temp_n == 0 || temp_n.is_power_of_two()) {
             let pad = temp_n.next_power_of_two() - temp_n;
@@ -231,6 +475,7 @@ impl<'a, 'b> ProverCS<'a, 'b> {
             }
         }
         let n = self.a_L.len();
+        let n2 = n - n1;
         if self.bp_gens.gens_capacity < n {
             return Err(R1CSError::InvalidGeneratorsLength);
         }
@@ -238,7 +483,8 @@ impl<'a, 'b> ProverCS<'a, 'b> {
         // We are performing a single-party circuit proof, so party index is 0.
         let gens = self.bp_gens.share(0);
 
-        // 1. Create a `TranscriptRng` from the high-level witness data
+        // 1. Create a `TranscriptRng` from the high-level witness data,
+        // finalized with the caller-supplied `rng`.
 
         let mut rng = {
             let mut builder = self.transcript.build_rng();
@@ -248,49 +494,61 @@ impl<'a, 'b> ProverCS<'a, 'b> {
                 builder = builder.commit_witness_bytes(b"v_blinding", v_b.as_bytes());
             }
 
-            use rand::thread_rng;
-            builder.finalize(&mut thread_rng())
+            builder.finalize(rng)
         };
 
-        // 3. Choose blinding factors and form commitments to low-level witness data
+        // 2. Choose phase-2 blinding factors and form commitments to the
+        // phase-2 low-level witness data.
 
-        let i_blinding = Scalar::random(&mut rng);
-        let o_blinding = Scalar::random(&mut rng);
-        let s_blinding = Scalar::random(&mut rng);
+        let i_blinding2 = Scalar::random(&mut rng);
+        let o_blinding2 = Scalar::random(&mut rng);
+        let s_blinding2 = Scalar::random(&mut rng);
 
-        let s_L: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
-        let s_R: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let s_L2: Vec<Scalar> = (0..n2).map(|_| Scalar::random(&mut rng)).collect();
+        let s_R2: Vec<Scalar> = (0..n2).map(|_| Scalar::random(&mut rng)).collect();
 
-        // A_I = <a_L, G> + <a_R, H> + i_blinding * B_blinding
-        let A_I = RistrettoPoint::multiscalar_mul(
-            iter::once(&i_blinding)
-                .chain(self.a_L.iter())
-                .chain(self.a_R.iter()),
+        // A_I2 = <a_L[n1..], G[n1..]> + <a_R[n1..], H[n1..]> + i_blinding2 * B_blinding
+        let A_I2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&i_blinding2)
+                .chain(self.a_L[n1..].iter())
+                .chain(self.a_R[n1..].iter()),
             iter::once(&self.pc_gens.B_blinding)
-                .chain(gens.G(n))
-                .chain(gens.H(n)),
+                .chain(gens.G(n).skip(n1))
+                .chain(gens.H(n).skip(n1)),
         )
         .compress();
 
-        // A_O = <a_O, G> + o_blinding * B_blinding
-        let A_O = RistrettoPoint::multiscalar_mul(
-            iter::once(&o_blinding).chain(self.a_O.iter()),
-            iter::once(&self.pc_gens.B_blinding).chain(gens.G(n)),
+        // A_O2 = <a_O[n1..], G[n1..]> + o_blinding2 * B_blinding
+        let A_O2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&o_blinding2).chain(self.a_O[n1..].iter()),
+            iter::once(&self.pc_gens.B_blinding).chain(gens.G(n).skip(n1)),
         )
         .compress();
 
-        // S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
-        let S = RistrettoPoint::multiscalar_mul(
-            iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
+        // S2 = <s_L2, G[n1..]> + <s_R2, H[n1..]> + s_blinding2 * B_blinding
+        let S2 = RistrettoPoint::multiscalar_mul(
+            iter::once(&s_blinding2).chain(s_L2.iter()).chain(s_R2.iter()),
             iter::once(&self.pc_gens.B_blinding)
-                .chain(gens.G(n))
-                .chain(gens.H(n)),
+                .chain(gens.G(n).skip(n1))
+                .chain(gens.H(n).skip(n1)),
         )
         .compress();
 
-        self.transcript.commit_point(b"A_I", &A_I);
-        self.transcript.commit_point(b"A_O", &A_O);
-        self.transcript.commit_point(b"S", &S);
+        self.transcript.commit_point(b"A_I2", &A_I2);
+        self.transcript.commit_point(b"A_O2", &A_O2);
+        self.transcript.commit_point(b"S2", &S2);
+
+        // Fold the two phases' blinding factors and s_L/s_R together: since
+        // A_I1 + A_I2 = <a_L, G> + <a_R, H> + (i_blinding1 + i_blinding2) * B_blinding
+        // (and likewise for A_O, S), the rest of the proof proceeds exactly
+        // as the single-phase case once these are combined.
+        let i_blinding = phase1.i_blinding1 + i_blinding2;
+        let o_blinding = phase1.o_blinding1 + o_blinding2;
+        let s_blinding = phase1.s_blinding1 + s_blinding2;
+        let mut s_L = phase1.s_L1;
+        s_L.extend(s_L2);
+        let mut s_R = phase1.s_R1;
+        s_R.extend(s_R2);
 
         // 4. Compute blinded vector polynomials l(x) and r(x)
 
@@ -390,9 +648,12 @@ impl<'a, 'b> ProverCS<'a, 'b> {
         );
 
         Ok(R1CSProof {
-            A_I,
-            A_O,
-            S,
+            A_I1: phase1.A_I1,
+            A_O1: phase1.A_O1,
+            S1: phase1.S1,
+            A_I2,
+            A_O2,
+            S2,
             T_1,
             T_3,
             T_4,
@@ -405,3 +666,181 @@ impl<'a, 'b> ProverCS<'a, 'b> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::traits::Identity;
+    use generators::{BulletproofGens, PedersenGens};
+
+    /// A second phase of multipliers, allocated from inside the
+    /// `specify_randomized_constraints` callback, can be proved over and
+    /// yields a proof with a non-identity `A_I2`. (`A_I2` always carries
+    /// a random blinding term, so this does not by itself show that the
+    /// phase-2 multiplier was included in it.)
+    #[test]
+    fn specify_randomized_constraints_adds_a_second_phase_of_multipliers() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(4, 1);
+        let mut transcript = Transcript::new(b"SpecifyRandomizedConstraintsTest");
+        let (mut cs, _vars, _commitments) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+
+        cs.assign_multiplier(
+            Scalar::from(2u64).into(),
+            Scalar::from(3u64).into(),
+            Scalar::from(6u64).into(),
+        )
+        .unwrap();
+
+        cs.specify_randomized_constraints(&mut thread_rng(), |cs, challenge| {
+            cs.assign_multiplier(challenge.into(), Scalar::one().into(), challenge.into())?;
+            Ok(())
+        })
+        .unwrap();
+
+        let proof = cs.prove().unwrap();
+        assert_ne!(proof.A_I2, RistrettoPoint::identity().compress());
+    }
+
+    #[test]
+    fn specify_randomized_constraints_cannot_be_called_twice() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(2, 1);
+        let mut transcript = Transcript::new(b"SpecifyRandomizedConstraintsTwiceTest");
+        let (mut cs, _vars, _commitments) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+
+        cs.specify_randomized_constraints(&mut thread_rng(), |_cs, _challenge| Ok(()))
+            .unwrap();
+
+        match cs.specify_randomized_constraints(&mut thread_rng(), |_cs, _challenge| Ok(())) {
+            Err(R1CSError::InvalidR1CSConstruction) => {}
+            other => panic!("expected InvalidR1CSConstruction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn prove_with_checks_catches_an_unsatisfied_constraint() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+        let mut transcript = Transcript::new(b"ProveWithChecksTest");
+        let (mut cs, _vars, _commitments) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+
+        let (_l, _r, o) = cs
+            .assign_multiplier(
+                Scalar::from(3u64).into(),
+                Scalar::from(2u64).into(),
+                Scalar::from(6u64).into(),
+            )
+            .unwrap();
+
+        // `o` is actually 6, so this constraint (o - 5 = 0) is false.
+        cs.add_constraint(LinearCombination::from(o) - LinearCombination::from(Scalar::from(5u64)));
+
+        match cs.prove_with_checks() {
+            Err(R1CSError::ConstraintNotSatisfied { index: 0, .. }) => {}
+            other => panic!("expected ConstraintNotSatisfied, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn prove_with_checks_accepts_a_satisfied_constraint_system() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+        let mut transcript = Transcript::new(b"ProveWithChecksOkTest");
+        let (mut cs, _vars, _commitments) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+
+        let (_l, _r, o) = cs
+            .assign_multiplier(
+                Scalar::from(3u64).into(),
+                Scalar::from(2u64).into(),
+                Scalar::from(6u64).into(),
+            )
+            .unwrap();
+
+        cs.add_constraint(LinearCombination::from(o) - LinearCombination::from(Scalar::from(6u64)));
+
+        assert!(cs.prove_with_checks().is_ok());
+    }
+
+    /// A minimal deterministic `RngCore + CryptoRng` source, so that
+    /// `prove_with_rng` can be exercised without pulling in an external
+    /// seedable-RNG dependency just for this test.
+    struct CountingRng(u64);
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(1);
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(8) {
+                chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for CountingRng {}
+
+    #[test]
+    fn prove_with_rng_is_deterministic_given_a_fixed_seed() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+
+        let build_proof = || {
+            let mut transcript = Transcript::new(b"ProveWithRngTest");
+            let (cs, _vars, _commitments) =
+                ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+            cs.prove_with_rng(&mut CountingRng(0)).unwrap()
+        };
+
+        assert_eq!(build_proof().to_bytes(), build_proof().to_bytes());
+    }
+
+    /// A constraint system that goes through
+    /// `specify_randomized_constraints` must be just as deterministic
+    /// under a fixed seed as one that doesn't: phase 1's blinding
+    /// factors come from the same `rng` the caller passes to
+    /// `specify_randomized_constraints`, not from `thread_rng()`.
+    #[test]
+    fn prove_with_rng_is_deterministic_with_randomized_constraints() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(4, 1);
+
+        let build_proof = || {
+            let mut transcript = Transcript::new(b"ProveWithRngRandomizedConstraintsTest");
+            let (mut cs, _vars, _commitments) =
+                ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+
+            cs.assign_multiplier(
+                Scalar::from(2u64).into(),
+                Scalar::from(3u64).into(),
+                Scalar::from(6u64).into(),
+            )
+            .unwrap();
+
+            cs.specify_randomized_constraints(&mut CountingRng(0), |cs, challenge| {
+                cs.assign_multiplier(challenge.into(), Scalar::one().into(), challenge.into())?;
+                Ok(())
+            })
+            .unwrap();
+
+            cs.prove_with_rng(&mut CountingRng(0)).unwrap()
+        };
+
+        assert_eq!(build_proof().to_bytes(), build_proof().to_bytes());
+    }
+}
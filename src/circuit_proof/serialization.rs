@@ -0,0 +1,204 @@
+//! Wire format for [`R1CSProof`].
+//!
+//! The proof is serialized as the concatenation of its fixed-size fields
+//! followed by the variable-length tail of the nested
+//! [`InnerProductProof`]:
+//!
+//! ```text
+//! [ A_I1 | A_O1 | S1 | A_I2 | A_O2 | S2 | T_1 | T_3 | T_4 | T_5 | T_6 ]   11 * 32 bytes
+//! [ t_x | t_x_blinding | e_blinding ]                                     3 * 32 bytes
+//! [ ipp_proof ]                                                           variable
+//! ```
+//!
+//! The `*1`/`*2` pairs are the phase-1 and phase-2 low-level witness
+//! commitments (see [`ProverCS::specify_randomized_constraints`]); a
+//! proof with no deferred constraints still has both, with phase 2 over
+//! only the zero padding up to a power of two (possibly empty). This mirrors the fixed-32-byte-per-element layout
+//! used by the aggregated range proof, and relies on
+//! [`InnerProductProof::from_bytes`] to recover its own `lg_n` round
+//! count from however many bytes remain after the fixed header, so no
+//! separate length field is needed.
+//!
+//! [`ProverCS::specify_randomized_constraints`]: super::prover::ProverCS::specify_randomized_constraints
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use super::R1CSProof;
+use errors::R1CSError;
+use inner_product_proof::InnerProductProof;
+
+/// Number of compressed Ristretto points in the fixed header.
+const N_POINTS: usize = 11;
+/// Number of scalars in the fixed header.
+const N_SCALARS: usize = 3;
+/// Size in bytes of the fixed header, before the `ipp_proof` tail.
+const HEADER_SIZE: usize = (N_POINTS + N_SCALARS) * 32;
+
+impl R1CSProof {
+    /// Serializes the proof into a byte array of the canonical wire
+    /// format described in the [module-level docs](self).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(self.A_I1.as_bytes());
+        buf.extend_from_slice(self.A_O1.as_bytes());
+        buf.extend_from_slice(self.S1.as_bytes());
+        buf.extend_from_slice(self.A_I2.as_bytes());
+        buf.extend_from_slice(self.A_O2.as_bytes());
+        buf.extend_from_slice(self.S2.as_bytes());
+        buf.extend_from_slice(self.T_1.as_bytes());
+        buf.extend_from_slice(self.T_3.as_bytes());
+        buf.extend_from_slice(self.T_4.as_bytes());
+        buf.extend_from_slice(self.T_5.as_bytes());
+        buf.extend_from_slice(self.T_6.as_bytes());
+        buf.extend_from_slice(self.t_x.as_bytes());
+        buf.extend_from_slice(self.t_x_blinding.as_bytes());
+        buf.extend_from_slice(self.e_blinding.as_bytes());
+        buf.extend_from_slice(&self.ipp_proof.to_bytes());
+        buf
+    }
+
+    /// Returns the size in bytes that [`R1CSProof::to_bytes`] would
+    /// produce, without actually serializing.
+    pub fn serialized_size(&self) -> usize {
+        // `InnerProductProof` doesn't expose a `serialized_size`, so fall
+        // back to measuring its own `to_bytes()` output.
+        HEADER_SIZE + self.ipp_proof.to_bytes().len()
+    }
+
+    /// Deserializes the proof from a byte slice produced by
+    /// [`R1CSProof::to_bytes`].
+    ///
+    /// Returns an error if the slice is too short to contain the fixed
+    /// header, if any point or scalar is not a canonical encoding, or if
+    /// the nested `InnerProductProof` fails to parse.
+    pub fn from_bytes(slice: &[u8]) -> Result<R1CSProof, R1CSError> {
+        if slice.len() < HEADER_SIZE {
+            return Err(R1CSError::FormatError);
+        }
+
+        let mut points = slice[..N_POINTS * 32].chunks(32).map(read_point);
+        let A_I1 = points.next().unwrap()?;
+        let A_O1 = points.next().unwrap()?;
+        let S1 = points.next().unwrap()?;
+        let A_I2 = points.next().unwrap()?;
+        let A_O2 = points.next().unwrap()?;
+        let S2 = points.next().unwrap()?;
+        let T_1 = points.next().unwrap()?;
+        let T_3 = points.next().unwrap()?;
+        let T_4 = points.next().unwrap()?;
+        let T_5 = points.next().unwrap()?;
+        let T_6 = points.next().unwrap()?;
+
+        let scalars_offset = N_POINTS * 32;
+        let mut scalars = slice[scalars_offset..HEADER_SIZE].chunks(32).map(read_scalar);
+        let t_x = scalars.next().unwrap()?;
+        let t_x_blinding = scalars.next().unwrap()?;
+        let e_blinding = scalars.next().unwrap()?;
+
+        let ipp_proof = InnerProductProof::from_bytes(&slice[HEADER_SIZE..])?;
+
+        Ok(R1CSProof {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1,
+            T_3,
+            T_4,
+            T_5,
+            T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp_proof,
+        })
+    }
+}
+
+fn read_point(bytes: &[u8]) -> Result<CompressedRistretto, R1CSError> {
+    let point = CompressedRistretto(copy32(bytes));
+    // Reject non-canonical encodings by requiring the point to actually
+    // decompress, rather than deferring the check to verification time.
+    point.decompress().ok_or(R1CSError::FormatError)?;
+    Ok(point)
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, R1CSError> {
+    Scalar::from_canonical_bytes(copy32(bytes)).ok_or(R1CSError::FormatError)
+}
+
+fn copy32(bytes: &[u8]) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    buf
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for R1CSProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for R1CSProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct R1CSProofVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for R1CSProofVisitor {
+            type Value = R1CSProof;
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                formatter.write_str("a valid R1CSProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<R1CSProof, E>
+            where
+                E: ::serde::de::Error,
+            {
+                R1CSProof::from_bytes(v).map_err(::serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(R1CSProofVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circuit_proof::prover::ProverCS;
+    use generators::{BulletproofGens, PedersenGens};
+    use merlin::Transcript;
+
+    #[test]
+    fn to_bytes_from_bytes_round_trips() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(1, 1);
+        let mut transcript = Transcript::new(b"R1CSProofSerializationTest");
+        let (cs, _vars, _commitments) =
+            ProverCS::new(&bp_gens, &pc_gens, &mut transcript, vec![], vec![]);
+        let proof = cs.prove().unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), proof.serialized_size());
+
+        let parsed = R1CSProof::from_bytes(&bytes).unwrap();
+        assert_eq!(bytes, parsed.to_bytes());
+    }
+
+    #[test]
+    fn from_bytes_rejects_a_truncated_header() {
+        let short = vec![0u8; HEADER_SIZE - 1];
+        assert!(R1CSProof::from_bytes(&short).is_err());
+    }
+}